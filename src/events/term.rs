@@ -1,30 +1,64 @@
-use std::sync::mpsc::{self, Receiver};
-use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use crossterm::event::{read, Event as CrossTermEvent};
+use crossterm::event::{poll, read, Event as CrossTermEvent};
 
-pub use crossterm::event::{KeyCode, KeyEvent};
+pub use crossterm::event::{KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
 
-type Rx = Receiver<Event>;
+use crate::ScreenSize;
 
 pub enum Event {
     Tick,
     Key(KeyEvent),
+    Mouse(MouseEvent),
+    Resize(ScreenSize),
 }
 
 // -----------------------------------------------------------------------------
 //     - Events -
 // -----------------------------------------------------------------------------
 pub struct Events {
-    rx: Rx,
+    // The time between two `Tick`s.
+    frame: Duration,
+    // When the next `Tick` is due.
+    next_tick: Instant,
 }
 
 impl Iterator for Events {
     type Item = Event;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.rx.recv().ok()
+        loop {
+            let now = Instant::now();
+
+            // The tick is due: emit it and schedule the next one. If we fell
+            // several frames behind (slow frame handling), coalesce the missed
+            // ticks into one rather than firing a burst to catch up.
+            if now >= self.next_tick {
+                self.next_tick += self.frame;
+                if self.next_tick <= now {
+                    self.next_tick = now + self.frame;
+                }
+                return Some(Event::Tick);
+            }
+
+            // Wait for input, but no longer than it takes for the next tick to
+            // come due.
+            let timeout = self.next_tick - now;
+            match poll(timeout) {
+                Ok(true) => match read() {
+                    Ok(CrossTermEvent::Key(k)) => return Some(Event::Key(k)),
+                    Ok(CrossTermEvent::Mouse(m)) => return Some(Event::Mouse(m)),
+                    Ok(CrossTermEvent::Resize(cols, rows)) => {
+                        return Some(Event::Resize(ScreenSize::new(cols, rows)))
+                    }
+                    // An event we don't surface, or a read error: keep waiting.
+                    _ => continue,
+                },
+                // Timed out (the tick is now due) or the poll failed: loop round
+                // and let the deadline check above emit the `Tick`.
+                _ => continue,
+            }
+        }
     }
 }
 
@@ -46,23 +80,11 @@ impl Iterator for Events {
 /// }
 /// ```
 pub fn events(fps: u64) -> Events {
-    let (tx, rx) = mpsc::channel();
-
-    // Input events
-    let tx_clone = tx.clone();
-    thread::spawn(move || loop {
-        if let Ok(ev) = read() {
-            if let CrossTermEvent::Key(k) = ev {
-                let _ = tx_clone.send(Event::Key(k));
-            }
-        }
-    });
-
-    // Frames
-    thread::spawn(move || loop {
-        let _ = tx.send(Event::Tick);
-        thread::sleep(Duration::from_millis(1000 / fps));
-    });
-
-    Events { rx }
+    // Clamp to at least 1ms per frame so an absurd fps can't collapse the
+    // frame to zero and spin the loop without ever polling for input.
+    let frame = Duration::from_millis((1000 / fps.max(1)).max(1));
+    Events {
+        frame,
+        next_tick: Instant::now(),
+    }
 }
@@ -1,19 +1,22 @@
+use std::collections::HashMap;
 use std::io::{self, Stdout, Write};
 
 use crossterm::cursor::{self, MoveTo};
 
-#[cfg(target_os = "windows")]
-use crossterm::event::EnableMouseCapture;
+use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
 
-#[cfg(not(target_os = "windows"))]
-use crossterm::event::DisableMouseCapture;
-
-use crossterm::style::Print;
+use crossterm::style::{
+    Attribute, Attributes, Color, Print, SetAttribute, SetBackgroundColor, SetForegroundColor,
+};
 use crossterm::terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType};
 use crossterm::QueueableCommand;
 use crossterm::{execute, ExecutableCommand, Result};
 
-use crate::{Pixel, Viewport};
+use crate::{Pixel, ScreenPos, Viewport};
+
+/// The text-style attributes a [`Pixel`] can carry, in the order they are
+/// re-applied after a style reset.
+const ATTRIBUTES: [Attribute; 3] = [Attribute::Bold, Attribute::Underlined, Attribute::Reverse];
 
 // -----------------------------------------------------------------------------
 //     - Raw mode -
@@ -21,18 +24,11 @@ use crate::{Pixel, Viewport};
 fn raw_mode() -> Result<Stdout> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    // we enable mouse capture because:
-    // 1) DisableMouseCapture doesn't work on windows without enabling it first
-    // 2) it allows to add mouse support later if needed
-    //
-    // ! if you want to disable mouse capture, be sure to enable it first,
-    // ! or it will crash on windows.
-    #[cfg(target_os = "windows")]
+    // we enable mouse capture so that mouse events are delivered through the
+    // events stream (see `Event::Mouse`). It is disabled again when the
+    // `StdoutTarget` is dropped.
     execute!(stdout, EnableMouseCapture,)?;
 
-    #[cfg(not(target_os = "windows"))]
-    execute!(stdout, DisableMouseCapture,)?;
-
     stdout.execute(cursor::Hide)?;
     stdout.execute(Clear(ClearType::All))?;
     Ok(stdout)
@@ -56,6 +52,15 @@ impl<T: RenderTarget> Renderer<T> {
     pub fn render(&mut self, viewport: &mut Viewport) {
         self.target.render(viewport.pixels());
     }
+
+    /// Invalidate the target's cached frame so the next [`render`](Self::render)
+    /// repaints everything.
+    ///
+    /// Call this after an [`Event::Resize`](crate::Event::Resize) or any other
+    /// event that corrupts the terminal contents.
+    pub fn force_redraw(&mut self) {
+        self.target.force_redraw();
+    }
 }
 
 // -----------------------------------------------------------------------------
@@ -64,11 +69,28 @@ impl<T: RenderTarget> Renderer<T> {
 /// Something that a render can render to.
 pub trait RenderTarget {
     fn render(&mut self, pixels: Vec<Pixel>);
+
+    /// Invalidate any cached frame so the next [`render`](Self::render)
+    /// repaints everything. Targets that don't cache anything can leave this
+    /// as the default no-op.
+    fn force_redraw(&mut self) {}
 }
 
 /// Render to stdout
 pub struct StdoutTarget {
     stdout: Stdout,
+    // The frame currently displayed on the terminal, so we can diff the next
+    // frame against it and only touch the cells that actually changed.
+    front: HashMap<ScreenPos, Pixel>,
+    // The cell written by the last `Print` (the cursor now sits just right of
+    // it), used to suppress a redundant `MoveTo` when the next cell is the one
+    // immediately to the right.
+    last_pos: Option<ScreenPos>,
+    // The style currently active on the terminal, so we only emit a style
+    // command when the next pixel differs from the last one we wrote.
+    fg: Option<Color>,
+    bg: Option<Color>,
+    attrs: Attributes,
 }
 
 impl StdoutTarget {
@@ -77,31 +99,260 @@ impl StdoutTarget {
     /// Once this is dropped it will disable raw mode.
     pub fn new() -> Result<Self> {
         let stdout = raw_mode()?;
-        Ok(Self { stdout })
+        Ok(Self {
+            stdout,
+            front: HashMap::new(),
+            last_pos: None,
+            fg: None,
+            bg: None,
+            attrs: Attributes::default(),
+        })
+    }
+
+    /// Move the cursor to `pos`, unless it is already there because the
+    /// previous `Print` advanced the cursor from the cell immediately to the
+    /// left.
+    fn move_to(&mut self, pos: ScreenPos) {
+        let adjacent = self
+            .last_pos
+            .map_or(false, |prev| prev.y == pos.y && prev.x + 1 == pos.x);
+
+        if !adjacent {
+            self.stdout
+                .queue(MoveTo(pos.x, pos.y))
+                .expect("failed to move cursor");
+        }
+    }
+
+    /// Queue the style commands needed to draw with `fg`, `bg` and `attrs`,
+    /// skipping the colors that match the style currently active on the
+    /// terminal. A change of attributes resets the whole style, so any color
+    /// that is set is then emitted again.
+    fn queue_style(&mut self, fg: Option<Color>, bg: Option<Color>, attrs: Attributes) {
+        // Attributes are handled first: there is no single command to clear one
+        // attribute, so we reset everything and re-apply the ones we want.
+        // `Attribute::Reset` is SGR 0, which also clears the colors, so we
+        // forget the cached colors to force them to be re-emitted below.
+        if attrs != self.attrs {
+            self.attrs = attrs;
+            self.fg = None;
+            self.bg = None;
+            self.stdout
+                .queue(SetAttribute(Attribute::Reset))
+                .expect("failed to reset attributes");
+            for attr in ATTRIBUTES {
+                if attrs.has(attr) {
+                    self.stdout
+                        .queue(SetAttribute(attr))
+                        .expect("failed to set attribute");
+                }
+            }
+        }
+
+        if fg != self.fg {
+            self.fg = fg;
+            self.stdout
+                .queue(SetForegroundColor(fg.unwrap_or(Color::Reset)))
+                .expect("failed to set foreground color");
+        }
+
+        if bg != self.bg {
+            self.bg = bg;
+            self.stdout
+                .queue(SetBackgroundColor(bg.unwrap_or(Color::Reset)))
+                .expect("failed to set background color");
+        }
     }
 }
 
 impl RenderTarget for StdoutTarget {
+    fn force_redraw(&mut self) {
+        self.front.clear();
+        // The old contents are untrustworthy, so wipe the screen immediately;
+        // flush now rather than waiting for the next frame, which might draw
+        // nothing and leave the wipe stuck in the buffer.
+        let _ = self.stdout.queue(Clear(ClearType::All));
+        let _ = self.stdout.flush();
+    }
+
     fn render(&mut self, pixels: Vec<Pixel>) {
+        // Build the frame we want to display, keyed by position.
+        let mut next: HashMap<ScreenPos, Pixel> = HashMap::with_capacity(pixels.len());
         for pixel in pixels {
-            self.stdout
-                .queue(MoveTo(pixel.pos.x, pixel.pos.y))
-                .expect("failed to move cursor");
+            next.insert(pixel.pos, pixel);
+        }
+
+        self.last_pos = None;
+
+        // Cells that are new or whose glyph/style changed since last frame.
+        // Sort by row then column so runs of changed cells stay contiguous and
+        // the `MoveTo` suppression in `move_to` actually fires.
+        let mut changed = next
+            .values()
+            .filter(|pixel| self.front.get(&pixel.pos) != Some(*pixel))
+            .cloned()
+            .collect::<Vec<_>>();
+        changed.sort_by_key(|pixel| (pixel.pos.y, pixel.pos.x));
+
+        for pixel in changed {
+            self.move_to(pixel.pos);
+            self.queue_style(pixel.fg, pixel.bg, pixel.attrs);
             self.stdout
                 .queue(Print(pixel.glyph.to_string()))
                 .expect("failed to print");
+            self.last_pos = Some(pixel.pos);
         }
 
-        let _ = self.stdout.flush();
+        // Cells drawn last frame but absent this frame: overwrite with a blank
+        // glyph so they don't linger on screen.
+        let mut stale = self
+            .front
+            .keys()
+            .filter(|pos| !next.contains_key(pos))
+            .copied()
+            .collect::<Vec<_>>();
+        stale.sort_by_key(|pos| (pos.y, pos.x));
+
+        for pos in stale {
+            self.move_to(pos);
+            self.queue_style(None, None, Attributes::default());
+            self.stdout
+                .queue(Print(' '.to_string()))
+                .expect("failed to print");
+            self.last_pos = Some(pos);
+        }
+
+        // If no cell was touched there is nothing to flush; staying silent on
+        // idle frames is the whole point of the diff renderer.
+        if self.last_pos.is_some() {
+            // Reset the style at the end of the frame so nothing bleeds past
+            // the rendered pixels, and forget what was active.
+            // `Attribute::Reset` (SGR 0) already clears the colors, so a
+            // separate `ResetColor` would be redundant.
+            let _ = self.stdout.queue(SetAttribute(Attribute::Reset));
+            self.fg = None;
+            self.bg = None;
+            self.attrs = Attributes::default();
+
+            let _ = self.stdout.flush();
+        }
+
+        self.front = next;
     }
 }
 
 impl Drop for StdoutTarget {
     fn drop(&mut self) {
+        let _ = execute!(self.stdout, DisableMouseCapture);
         let _ = disable_raw_mode();
     }
 }
 
+/// Render to any [`Write`]r, e.g. a file, a pipe or an in-memory buffer.
+///
+/// Unlike [`StdoutTarget`] this has no raw-mode side effects, so it is safe to
+/// use outside of an interactive terminal — for golden-file tests or for
+/// non-interactive output.
+pub struct WriteTarget<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> WriteTarget<W> {
+    /// Create a new target over `writer`.
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Consume the target and return the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> RenderTarget for WriteTarget<W> {
+    fn render(&mut self, pixels: Vec<Pixel>) {
+        for pixel in pixels {
+            self.writer
+                .queue(MoveTo(pixel.pos.x, pixel.pos.y))
+                .expect("failed to move cursor");
+
+            if let Some(fg) = pixel.fg {
+                self.writer
+                    .queue(SetForegroundColor(fg))
+                    .expect("failed to set foreground color");
+            }
+            if let Some(bg) = pixel.bg {
+                self.writer
+                    .queue(SetBackgroundColor(bg))
+                    .expect("failed to set background color");
+            }
+            for attr in ATTRIBUTES {
+                if pixel.attrs.has(attr) {
+                    self.writer
+                        .queue(SetAttribute(attr))
+                        .expect("failed to set attribute");
+                }
+            }
+
+            self.writer
+                .queue(Print(pixel.glyph.to_string()))
+                .expect("failed to print");
+            self.writer
+                .queue(SetAttribute(Attribute::Reset))
+                .expect("failed to reset style");
+        }
+
+        let _ = self.writer.flush();
+    }
+}
+
+/// Compose a frame into a plain `String` instead of driving a terminal.
+///
+/// Every [`render`](RenderTarget::render) lays the pixels out onto a blank grid
+/// and replaces the accumulated [`contents`](Self::contents), so a single scene
+/// can be rendered once and captured — handy for deterministic tests and for
+/// printing a scene non-interactively. Colors and attributes are dropped; only
+/// the glyphs are kept.
+#[derive(Default)]
+pub struct StringTarget {
+    contents: String,
+}
+
+impl StringTarget {
+    /// Create a new, empty target.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The most recently composed frame.
+    pub fn contents(&self) -> &str {
+        &self.contents
+    }
+}
+
+impl RenderTarget for StringTarget {
+    fn render(&mut self, pixels: Vec<Pixel>) {
+        if pixels.is_empty() {
+            self.contents.clear();
+            return;
+        }
+
+        let width = pixels.iter().map(|p| p.pos.x).max().unwrap_or(0) as usize;
+        let height = pixels.iter().map(|p| p.pos.y).max().unwrap_or(0) as usize;
+
+        let mut grid = vec![vec![' '; width + 1]; height + 1];
+        for pixel in &pixels {
+            grid[pixel.pos.y as usize][pixel.pos.x as usize] = pixel.glyph;
+        }
+
+        self.contents = grid
+            .into_iter()
+            .map(|row| row.into_iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -149,4 +400,24 @@ mod test {
         let pixels = vec![a];
         assert_eq!(pixels, renderer.target.pixels);
     }
+
+    #[test]
+    fn render_to_string() {
+        let cam = camera();
+        let mut view = viewport();
+
+        let min_x = cam.bounding_box.min_x();
+        let min_y = cam.bounding_box.min_y();
+
+        let a = ('A', WorldPos::new(min_x, min_y));
+        let a = (a.0, cam.to_screen(a.1));
+
+        view.draw_pixel(a);
+        let mut renderer = Renderer::new(StringTarget::new());
+
+        renderer.render(&mut view);
+
+        // The glyph ends up at screen (2, 2) because of the viewport offset.
+        assert_eq!('A', renderer.target.contents().chars().last().unwrap());
+    }
 }